@@ -1,6 +1,6 @@
+use bowie_tracker::api::{RequestContext, DEFAULT_INSTANCE};
 use bowie_tracker::models::{ListenBrainzResponse, BowieLookup};
 use bowie_tracker::analytics::is_bowie_meta;
-use reqwest::Client;
 use std::env;
 use std::fs::File;
 use std::io::BufReader;
@@ -9,30 +9,27 @@ use std::io::BufReader;
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: cargo run --example verify_user <username> [token]");
+        eprintln!("Usage: cargo run --example verify_user <username> [token] [instance]");
         std::process::exit(1);
     }
 
     let user = &args[1];
-    let token = args.get(2);
+    let token = args.get(2).cloned().unwrap_or_default();
+    let instance = args.get(3).cloned().unwrap_or_else(|| DEFAULT_INSTANCE.to_string());
+
+    let ctx = RequestContext::new(instance.clone()).auth(token);
 
     println!("Loading bowie_lookup.json...");
     let file = File::open("bowie_lookup.json").expect("Failed to open bowie_lookup.json");
     let reader = BufReader::new(file);
     let lookup: BowieLookup = serde_json::from_reader(reader).expect("Failed to parse lookup");
 
-    println!("--- Headless Verification for user: {} ---", user);
-
-    let url = format!("https://api.listenbrainz.org/1/user/{}/listens?count=100", user);
-    println!("Fetching from: {}", url);
+    println!("--- Headless Verification for user: {} (instance: {}) ---", user, instance);
 
-    let client = Client::new();
-    let mut req = client.get(&url);
-    if let Some(t) = token {
-        req = req.header("Authorization", format!("Token {}", t));
-    }
+    let path = format!("/1/user/{}/listens?count=100", user);
+    println!("Fetching from: {}{}", instance, path);
 
-    let resp = req.send().await?;
+    let resp = ctx.get(&path).send().await?;
 
     if !resp.status().is_success() {
         eprintln!("API Error: {}", resp.status());
@@ -45,13 +42,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Fetched {} listens.", listens.len());
 
     // Check Playing Now
-    let np_url = format!("https://api.listenbrainz.org/1/user/{}/playing-now", user);
-    println!("Checking Playing Now: {}", np_url);
-    let mut np_req = client.get(&np_url);
-    if let Some(t) = token {
-        np_req = np_req.header("Authorization", format!("Token {}", t));
-    }
-    if let Ok(np_resp) = np_req.send().await {
+    let np_path = format!("/1/user/{}/playing-now", user);
+    println!("Checking Playing Now: {}{}", instance, np_path);
+    if let Ok(np_resp) = ctx.get(&np_path).send().await {
         if let Ok(np_json) = np_resp.json::<bowie_tracker::models::PlayingNowResponse>().await {
             if let Some(track) = np_json.payload.listens.first() {
                 println!("--- PLAYING NOW DETECTED ---");
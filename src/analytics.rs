@@ -24,7 +24,7 @@
 
 use chrono::{DateTime, Utc, TimeZone, Datelike, Timelike, Duration};
 use std::collections::{HashMap, HashSet};
-use crate::models::{Listen, BowieDatabase};
+use crate::models::{Listen, BowieDatabase, BowieReleaseGroup};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, PartialEq, Default)]
@@ -41,7 +41,8 @@ pub struct DashboardMetrics {
     pub insights: Vec<Insight>,
     
     // Chart Data
-    pub yearly_distribution: Vec<(i32, usize)>, // Year -> Scrobble Count
+    pub yearly_distribution: Vec<(i32, usize)>, // Album Release Year -> Scrobble Count
+    pub era_distribution: Vec<(i32, usize)>, // Album Release Decade -> Scrobble Count
     pub album_completion: Vec<(String, f64, Option<String>)>, // Title, %, Image
     pub monthly_volume: Vec<(String, usize)>, // Label -> Count
     pub track_time_leaderboard: Vec<(String, i64)>, // Track -> Minutes
@@ -51,6 +52,10 @@ pub struct DashboardMetrics {
     pub consistency_grid: Vec<(i64, usize)>, // Last 30 days TS -> Count
     pub album_weight: Vec<(String, usize, Option<String>)>, // Title, Count, Image
     pub forgotten_classics: Vec<(String, i64, usize)>, // Title, Days Idle, Total Count
+
+    // Local library ownership (see `library` module)
+    pub ownership: Vec<(String, usize, usize)>, // Title, Owned Tracks, Total Tracks
+    pub owned_track_ids: HashSet<String>, // Recording MBIDs confirmed owned locally; lets charts flag a track "owned" without widening every track tuple.
 }
 
 #[derive(Clone, PartialEq, Default)]
@@ -108,7 +113,7 @@ struct DayWork {
     track_ms: HashMap<String, i64>,
 }
 
-pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, external_counts: &HashMap<String, usize>, bowie_db: Option<&BowieDatabase>) -> DashboardMetrics {
+pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, external_counts: &HashMap<String, usize>, bowie_db: Option<&BowieDatabase>, owned_mbids: Option<&HashSet<String>>, resolved_tracks: Option<&HashMap<(String, String), crate::resolution::ResolvedTrack>>) -> DashboardMetrics {
     // Pre-calculate bowie MBID, Title, and Duration maps for fast lookup
     let mut bowie_mbids = HashSet::new();
     let mut bowie_durations = HashMap::new();
@@ -118,11 +123,11 @@ pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, ex
         for rg in db.release_groups.values() {
             for track in &rg.tracks {
                 bowie_mbids.insert(track.id.clone());
-                
+
                 // Index by MBID
                 let m_entry = bowie_durations.entry(track.id.clone()).or_insert(0);
                 if track.duration_ms > *m_entry { *m_entry = track.duration_ms; }
-                
+
                 // Index by Literal Title
                 let t_entry = bowie_title_durations.entry(track.title.clone()).or_insert(0);
                 if track.duration_ms > *t_entry { *t_entry = track.duration_ms; }
@@ -130,6 +135,31 @@ pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, ex
         }
     }
 
+    // Cross-service resolutions (`resolution::resolve_track`) for scrobbles
+    // that arrived without their own `mbid_mapping`. Resolved recording MBIDs
+    // join `bowie_mbids`/`bowie_durations`, and the period aggregation below
+    // falls back to the resolution keyed by `(artist_name, track_name)` for a
+    // listen's duration, so an unmapped listen's minutes count in the period
+    // totals. The track-time leaderboard looks durations up by the listen's
+    // own MBID only, so an unmapped listen still adds zero minutes there.
+    if let Some(resolved) = resolved_tracks {
+        for track in resolved.values() {
+            if let Some(id) = &track.mapping.recording_mbid {
+                bowie_mbids.insert(id.clone());
+                let m_entry = bowie_durations.entry(id.clone()).or_insert(0);
+                if track.duration_ms > *m_entry { *m_entry = track.duration_ms; }
+            }
+        }
+    }
+
+    // Local library ownership (`crate::library`), compared against listened
+    // completion in `DashboardMetrics::ownership`/`owned_track_ids`.
+    let owned_track_ids = owned_mbids.cloned().unwrap_or_default();
+    let ownership = match (bowie_db, owned_mbids) {
+        (Some(db), Some(owned)) => crate::library::ownership_by_album(db, owned),
+        _ => Vec::new(),
+    };
+
     // ... filtering ...
 
     for listen in &bowie_listens {
@@ -145,7 +175,12 @@ pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, ex
 
         let duration_ms = mbid.and_then(|id| bowie_durations.get(id).cloned())
             .or_else(|| bowie_title_durations.get(&track_name).cloned())
-            .unwrap_or(0); 
+            .or_else(|| {
+                resolved_tracks
+                    .and_then(|map| map.get(&(listen.track_metadata.artist_name.clone(), listen.track_metadata.track_name.clone())))
+                    .map(|resolved| resolved.duration_ms)
+            })
+            .unwrap_or(0);
         
         let album_name = listen.track_metadata.mbid_mapping.as_ref()
             .and_then(|m| m.release_name.clone())
@@ -181,12 +216,12 @@ pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, ex
         let mut stats = DayStats {
             timestamp: *day_ts,
             date_label: Utc.timestamp_opt(*day_ts, 0).unwrap().format("%a, %b %d").to_string(),
-            albums_completed: calculate_total_completion(work, external_counts, bowie_db),
+            albums_completed: calculate_total_completion(work, external_counts, bowie_db, Some(&studio_albums_only)),
             minutes: work.ms / 60000,
             scrobbles: work.scrobbles,
             ..Default::default()
         };
-        stats.top_albums = get_top_albums(&work.album_counts, &work.album_ms, 5, external_counts, bowie_db);
+        stats.top_albums = get_top_albums(&work.album_counts, &work.album_ms, 5, external_counts, bowie_db, Some(&studio_albums_only));
         stats.top_tracks = get_top_items(&work.track_counts, &work.track_ms, 5);
         stats.favorite_album = stats.top_albums.first().map(|a| a.0.clone()).unwrap_or_default();
         day_stats_map.insert(*day_ts, stats);
@@ -257,8 +292,9 @@ pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, ex
 
     // --- START CHART CALCULATIONS ---
     
-    // 1. Yearly Distribution
+    // 1. Yearly Distribution (by release era, not scrobble date)
     let mut year_map = HashMap::new();
+    let mut era_map: HashMap<i32, usize> = HashMap::new();
     // 2. Album Completion
     let mut album_unique_tracks: HashMap<String, HashSet<String>> = HashMap::new();
     // 4. Track Time Leaderboard
@@ -313,50 +349,102 @@ pub fn calculate_metrics(listens: &[Listen], now: DateTime<Utc>, basis: &str, ex
             album_unique_tracks.entry(album_name.clone()).or_insert_with(HashSet::new).insert(id.clone());
         }
 
-        // Year/Type via bowie_db
+        // Year/Era/Type via bowie_db: attribute scrobbles to the *release
+        // era* of the album, not the date they happened to be played.
+        let mut found_year = None;
         if let Some(db) = bowie_db {
             // Find which RG this track/album belongs to
-            let mut found_year = None;
             let mut found_type = None;
             for rg in db.release_groups.values() {
-                if rg.title == album_name || rg.tracks.iter().any(|t| Some(&t.id) == mbid) {
-                    // Try to extract year from first-release-date if we had it, 
-                    // but for now we only have RG title/type.
-                    // Let's assume we'll use the scrobble year as a proxy for "affinity year" 
-                    // unless we improve the metadata later.
-                    found_type = rg.release_type.clone();
+                if rg.matches_title(&album_name) || rg.tracks.iter().any(|t| Some(&t.id) == mbid) {
+                    found_type = Some(type_label(rg));
+                    found_year = rg.first_release_date.map(|d| d.year);
                     break;
                 }
             }
             if let Some(t) = found_type { *type_map.entry(t).or_insert(0) += 1; }
         }
-        *year_map.entry(dt.year()).or_insert(0) += 1;
+        // Fall back to the scrobble year only when the album has no known release date.
+        let era_year = found_year.unwrap_or_else(|| dt.year());
+        *year_map.entry(era_year).or_insert(0) += 1;
+        *era_map.entry((era_year / 10) * 10).or_insert(0) += 1;
 
         // Forgotten Classics
         *total_count_map.entry(track_name.clone()).or_insert(0) += 1;
         let entry = last_seen_map.entry(track_name.clone()).or_insert(0);
         if ts > *entry { *entry = ts; }
+    }
+
+    let mut yearly_distribution: Vec<_> = year_map.into_iter().collect();
+    yearly_distribution.sort_by_key(|(year, _)| *year);
+    metrics.yearly_distribution = yearly_distribution;
 
-fn get_bowie_album_tracks(name: &str, external_counts: &HashMap<String, usize>, bowie_db: Option<&BowieDatabase>) -> f64 {
-    // 1. Check MusicBrainz metadata
+    let mut era_distribution: Vec<_> = era_map.into_iter().collect();
+    era_distribution.sort_by_key(|(decade, _)| *decade);
+    metrics.era_distribution = era_distribution;
+
+    let mut type_distribution: Vec<_> = type_map.into_iter().collect();
+    type_distribution.sort_by(|a: &(String, usize), b: &(String, usize)| b.1.cmp(&a.1));
+    metrics.type_distribution = type_distribution;
+
+    metrics
+}
+
+/// Human label for a release group's typed taxonomy, e.g. `"Album"` or
+/// `"Album (Live, Compilation)"`. Used for the full `type_distribution`
+/// breakdown, which — unlike completion — reports every type as-is.
+fn type_label(rg: &BowieReleaseGroup) -> String {
+    let primary = rg.primary_type.map(|t| format!("{:?}", t)).unwrap_or_else(|| "Unknown".to_string());
+    if rg.secondary_types.is_empty() {
+        primary
+    } else {
+        let secondaries: Vec<String> = rg.secondary_types.iter().map(|t| format!("{:?}", t)).collect();
+        format!("{} ({})", primary, secondaries.join(", "))
+    }
+}
+
+/// A [`get_bowie_album_tracks`]/[`calculate_total_completion`] filter that
+/// keeps only the core studio discography — e.g. excludes `David Live`,
+/// `Stage`, and `ChangesOneBowie`-style compilations from completion math.
+pub fn studio_albums_only(rg: &BowieReleaseGroup) -> bool {
+    rg.is_studio_album()
+}
+
+/// Resolves `name`'s track count, or `None` if `filter` rejects the release
+/// group it matched (e.g. a studio-only filter rejecting a live album).
+/// `bowie_db` (populated by [`crate::musicbrainz::sync::build_bowie_database`])
+/// is checked first; only albums it doesn't cover fall through to the
+/// hardcoded ladder below, which exists purely as a last-resort offline
+/// fallback and is never filtered, since we don't know its entries' types.
+fn get_bowie_album_tracks(
+    name: &str,
+    external_counts: &HashMap<String, usize>,
+    bowie_db: Option<&BowieDatabase>,
+    filter: Option<&dyn Fn(&BowieReleaseGroup) -> bool>,
+) -> Option<f64> {
+    // 1. Check MusicBrainz metadata, matching either the canonical title or
+    // any alias folded in by `Merge::merge_in_place` (e.g. a scrobble's
+    // release name disagreeing with MusicBrainz on punctuation).
     if let Some(db) = bowie_db {
-        let name_low = name.to_lowercase();
         for rg in db.release_groups.values() {
-            if rg.title.to_lowercase() == name_low {
-                return rg.track_count as f64;
+            if rg.matches_title(name) {
+                return match filter {
+                    Some(f) if !f(rg) => None,
+                    _ => Some(rg.track_count as f64),
+                };
             }
         }
     }
 
     let n = name.to_lowercase();
-    
+
     // 2. Check external counts (from IndexedDB/MB API)
     if let Some(count) = external_counts.get(name) {
-        return *count as f64;
+        return Some(*count as f64);
     }
 
     // 3. Comprehensive Hardcoded List
-    if n.contains("david bowie") || n.contains("space oddity") { 10.0 }
+    Some(if n.contains("david bowie") || n.contains("space oddity") { 10.0 }
     else if n.contains("man who sold the world") { 9.0 }
     else if n.contains("hunky dory") { 11.0 }
     else if n.contains("ziggy stardust") { 11.0 }
@@ -385,21 +473,36 @@ fn get_bowie_album_tracks(name: &str, external_counts: &HashMap<String, usize>,
     else if n.contains("david live") { 17.0 }
     else if n.contains("stage") { 17.0 }
     else if n.contains("the motion picture") { 15.0 }
-    else { 11.0 } // Safe fallback
+    else { 11.0 }) // Safe fallback
 }
 
-fn calculate_total_completion(work: &DayWork, external_counts: &HashMap<String, usize>, bowie_db: Option<&BowieDatabase>) -> f64 {
+fn calculate_total_completion(
+    work: &DayWork,
+    external_counts: &HashMap<String, usize>,
+    bowie_db: Option<&BowieDatabase>,
+    filter: Option<&dyn Fn(&BowieReleaseGroup) -> bool>,
+) -> f64 {
     let mut total = 0.0;
     for (name, count) in &work.album_counts {
-        total += *count as f64 / get_bowie_album_tracks(name, external_counts, bowie_db);
+        if let Some(tracks) = get_bowie_album_tracks(name, external_counts, bowie_db, filter) {
+            total += *count as f64 / tracks;
+        }
     }
     total
 }
 
-fn get_top_albums(counts: &HashMap<String, usize>, mins: &HashMap<String, i64>, n: usize, external_counts: &HashMap<String, usize>, bowie_db: Option<&BowieDatabase>) -> Vec<(String, f64, i64)> {
-    let mut items: Vec<_> = counts.iter().map(|(name, &c)| {
-        let completion = c as f64 / get_bowie_album_tracks(name, external_counts, bowie_db);
-        (name.clone(), completion, *mins.get(name).unwrap_or(&0))
+fn get_top_albums(
+    counts: &HashMap<String, usize>,
+    mins: &HashMap<String, i64>,
+    n: usize,
+    external_counts: &HashMap<String, usize>,
+    bowie_db: Option<&BowieDatabase>,
+    filter: Option<&dyn Fn(&BowieReleaseGroup) -> bool>,
+) -> Vec<(String, f64, i64)> {
+    let mut items: Vec<_> = counts.iter().filter_map(|(name, &c)| {
+        let tracks = get_bowie_album_tracks(name, external_counts, bowie_db, filter)?;
+        let completion = c as f64 / tracks;
+        Some((name.clone(), completion, *mins.get(name).unwrap_or(&0)))
     }).collect();
     items.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
     items.into_iter().take(n).collect()
@@ -1,46 +1,179 @@
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::Arc;
 use chrono::Utc;
 use gloo_timers::future::sleep;
 use std::time::Duration;
 use web_sys::console;
 
-static RATELIMIT_REMAINING: AtomicUsize = AtomicUsize::new(30);
-static RATELIMIT_RESET_AT: AtomicI64 = AtomicI64::new(0);
+use crate::cache::AsyncCache;
+use crate::models::{ListenBrainzResponse, PlayingNowResponse};
 
-pub async fn fetch_with_rate_limit(url: &str, token: &str) -> Result<reqwest::Response, String> {
-    let now = Utc::now().timestamp();
-    let reset_at = RATELIMIT_RESET_AT.load(Ordering::Relaxed);
-    let remaining = RATELIMIT_REMAINING.load(Ordering::Relaxed);
+/// The public ListenBrainz instance, used when nothing else is configured.
+pub const DEFAULT_INSTANCE: &str = "https://api.listenbrainz.org";
 
-    // If we are getting close to the limit (e.g., < 2 remaining), 
-    // and the reset time is in the future, wait a bit.
-    if remaining < 2 && now < reset_at {
-        let wait_ms = ((reset_at - now) * 1000).max(100);
-        console::log_1(&format!("API Limit close. Throttling for {}ms...", wait_ms).into());
-        sleep(Duration::from_millis(wait_ms as u64)).await;
-    }
+/// How long a cached "playing now" result stays fresh before we re-poll.
+pub const PLAYING_NOW_INTERVAL_MS: f64 = 10_000.0;
+/// A fetch error isn't a confirmed "nothing playing" — retry sooner than a
+/// real result would warrant instead of suppressing polling for the full
+/// [`PLAYING_NOW_INTERVAL_MS`].
+pub const PLAYING_NOW_ERROR_INTERVAL_MS: f64 = 2_000.0;
+
+/// How long a cached 100-listen window stays fresh.
+pub const RECENT_LISTENS_INTERVAL_MS: f64 = 30_000.0;
+/// Same reasoning as [`PLAYING_NOW_ERROR_INTERVAL_MS`], scaled to this
+/// cache's longer [`RECENT_LISTENS_INTERVAL_MS`].
+pub const RECENT_LISTENS_ERROR_INTERVAL_MS: f64 = 5_000.0;
+
+/// Everything needed to talk to one ListenBrainz-compatible instance: its
+/// base URL, an auth token, a shared `reqwest::Client`, and the rate-limit
+/// state for *that* instance. Self-hosted ListenBrainz/Funkwhale servers each
+/// get their own `RequestContext` so their rate limits don't bleed into one
+/// another.
+#[derive(Clone)]
+pub struct RequestContext {
+    instance: String,
+    token: String,
+    client: reqwest::Client,
+    ratelimit_remaining: Arc<AtomicUsize>,
+    ratelimit_reset_at: Arc<AtomicI64>,
+}
 
-    let client = reqwest::Client::new();
-    let mut req = client.get(url);
-    if !token.is_empty() {
-        req = req.header("Authorization", format!("Token {}", token));
+impl RequestContext {
+    /// Points at `instance` (e.g. `https://api.listenbrainz.org`) with no
+    /// auth token set.
+    pub fn new(instance: impl Into<String>) -> Self {
+        Self {
+            instance: instance.into(),
+            token: String::new(),
+            client: reqwest::Client::new(),
+            ratelimit_remaining: Arc::new(AtomicUsize::new(30)),
+            ratelimit_reset_at: Arc::new(AtomicI64::new(0)),
+        }
     }
 
-    let resp = req.send().await.map_err(|e| e.to_string())?;
+    /// Attaches a token used as `Authorization: Token <token>` on every request.
+    pub fn auth(mut self, token: impl Into<String>) -> Self {
+        self.token = token.into();
+        self
+    }
 
-    // Update rate limit state from headers
-    if let Some(rem) = resp.headers().get("x-ratelimit-remaining") {
-        if let Ok(val) = rem.to_str().unwrap_or_default().parse::<usize>() {
-            RATELIMIT_REMAINING.store(val, Ordering::Relaxed);
+    /// Builds a GET request for `path` against this instance, with the
+    /// `Authorization` header attached when a token is set.
+    pub fn get<S: AsRef<str>>(&self, path: S) -> reqwest::RequestBuilder {
+        let url = format!("{}{}", self.instance.trim_end_matches('/'), path.as_ref());
+        let mut req = self.client.get(url);
+        if !self.token.is_empty() {
+            req = req.header("Authorization", format!("Token {}", self.token));
         }
+        req
     }
-    if let Some(reset) = resp.headers().get("x-ratelimit-reset") {
-        if let Ok(val) = reset.to_str().unwrap_or_default().parse::<i64>() {
-            // Note: ListenBrainz reset header is a Unix timestamp
-            RATELIMIT_RESET_AT.store(val, Ordering::Relaxed);
+
+    /// Issues a GET to `path`, throttling when this instance's rate limit is
+    /// close to exhausted and recording the updated limit from the response.
+    pub async fn fetch_with_rate_limit(&self, path: &str) -> Result<reqwest::Response, String> {
+        let now = Utc::now().timestamp();
+        let reset_at = self.ratelimit_reset_at.load(Ordering::Relaxed);
+        let remaining = self.ratelimit_remaining.load(Ordering::Relaxed);
+
+        // If we are getting close to the limit (e.g., < 2 remaining),
+        // and the reset time is in the future, wait a bit.
+        if remaining < 2 && now < reset_at {
+            let wait_ms = ((reset_at - now) * 1000).max(100);
+            console::log_1(&format!("API Limit close. Throttling for {}ms...", wait_ms).into());
+            sleep(Duration::from_millis(wait_ms as u64)).await;
+        }
+
+        let resp = self.get(path).send().await.map_err(|e| e.to_string())?;
+
+        // Update rate limit state from headers
+        if let Some(rem) = resp.headers().get("x-ratelimit-remaining") {
+            if let Ok(val) = rem.to_str().unwrap_or_default().parse::<usize>() {
+                self.ratelimit_remaining.store(val, Ordering::Relaxed);
+            }
         }
+        if let Some(reset) = resp.headers().get("x-ratelimit-reset") {
+            if let Ok(val) = reset.to_str().unwrap_or_default().parse::<i64>() {
+                // Note: ListenBrainz reset header is a Unix timestamp
+                self.ratelimit_reset_at.store(val, Ordering::Relaxed);
+            }
+        }
+
+        Ok(resp)
+    }
+
+    /// Fetches and parses the `playing-now` endpoint for `user`.
+    pub async fn fetch_playing_now(&self, user: &str) -> Result<PlayingNowResponse, String> {
+        let path = format!("/1/user/{}/playing-now", user);
+        let resp = self.fetch_with_rate_limit(&path).await?;
+        resp.json().await.map_err(|e| e.to_string())
+    }
+
+    /// Fetches and parses the most recent 100-listen window for `user`.
+    pub async fn fetch_recent_listens(&self, user: &str) -> Result<ListenBrainzResponse, String> {
+        let path = format!("/1/user/{}/listens?count=100", user);
+        let resp = self.fetch_with_rate_limit(&path).await?;
+        resp.json().await.map_err(|e| e.to_string())
+    }
+}
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// Memoizes `RequestContext::fetch_playing_now` per username, re-polling at
+/// most every [`PLAYING_NOW_INTERVAL_MS`] so a dashboard timer doesn't
+/// hammer the API. Callers should read it with `get_with_ttl(user,
+/// playing_now_ttl)` rather than plain `get` so a transient fetch error
+/// doesn't suppress polling for the full interval — see [`playing_now_ttl`].
+pub type PlayingNowCache =
+    AsyncCache<String, Result<PlayingNowResponse, String>, Box<dyn FnMut(&String) -> BoxFuture<'static, Result<PlayingNowResponse, String>>>>;
+
+pub fn playing_now_cache(ctx: RequestContext) -> PlayingNowCache {
+    AsyncCache::new(
+        Box::new(move |user: &String| {
+            let ctx = ctx.clone();
+            let user = user.clone();
+            Box::pin(async move { ctx.fetch_playing_now(&user).await }) as BoxFuture<'static, _>
+        }),
+        PLAYING_NOW_INTERVAL_MS,
+    )
+}
+
+/// The [`AsyncCache::get_with_ttl`] policy for [`PlayingNowCache`]: a
+/// successful fetch is good for [`PLAYING_NOW_INTERVAL_MS`], but an error
+/// is retried after only [`PLAYING_NOW_ERROR_INTERVAL_MS`] instead of
+/// being memoized as "nothing playing" for the full interval.
+pub fn playing_now_ttl(result: &Result<PlayingNowResponse, String>) -> f64 {
+    match result {
+        Ok(_) => PLAYING_NOW_INTERVAL_MS,
+        Err(_) => PLAYING_NOW_ERROR_INTERVAL_MS,
     }
+}
 
-    Ok(resp)
+/// Memoizes `RequestContext::fetch_recent_listens` per username, re-polling
+/// at most every [`RECENT_LISTENS_INTERVAL_MS`]. Callers should read it with
+/// `get_with_ttl(user, recent_listens_ttl)` rather than plain `get` — see
+/// [`recent_listens_ttl`].
+pub type RecentListensCache =
+    AsyncCache<String, Result<ListenBrainzResponse, String>, Box<dyn FnMut(&String) -> BoxFuture<'static, Result<ListenBrainzResponse, String>>>>;
+
+pub fn recent_listens_cache(ctx: RequestContext) -> RecentListensCache {
+    AsyncCache::new(
+        Box::new(move |user: &String| {
+            let ctx = ctx.clone();
+            let user = user.clone();
+            Box::pin(async move { ctx.fetch_recent_listens(&user).await }) as BoxFuture<'static, _>
+        }),
+        RECENT_LISTENS_INTERVAL_MS,
+    )
 }
 
+/// The [`AsyncCache::get_with_ttl`] policy for [`RecentListensCache`]: a
+/// successful fetch is good for [`RECENT_LISTENS_INTERVAL_MS`], but an
+/// error is retried after only [`RECENT_LISTENS_ERROR_INTERVAL_MS`].
+pub fn recent_listens_ttl(result: &Result<ListenBrainzResponse, String>) -> f64 {
+    match result {
+        Ok(_) => RECENT_LISTENS_INTERVAL_MS,
+        Err(_) => RECENT_LISTENS_ERROR_INTERVAL_MS,
+    }
+}
@@ -0,0 +1,89 @@
+//! Interval-based memoization for async fetches.
+//!
+//! `AsyncCache` wraps an async lookup function and skips the call entirely
+//! when the last result for a given key is still "fresh" (younger than the
+//! configured `interval`, in milliseconds). This is meant to sit in front of
+//! anything that hits a rate-limited HTTP API — repeated dashboard refreshes
+//! within the same interval read the cached value instead of burning budget.
+//!
+//! [`AsyncCache::get_with_ttl`] lets a caller shorten that freshness window
+//! per result, so a fetch that came back as a transient failure rather than
+//! a confirmed answer can be retried sooner instead of being memoized for
+//! the full interval.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+
+/// Memoizes the async results of `func` for `interval` milliseconds per key.
+pub struct AsyncCache<K, V, F> {
+    func: F,
+    cache: HashMap<K, (f64, f64, V)>,
+    interval: f64,
+}
+
+impl<K, V, F, Fut> AsyncCache<K, V, F>
+where
+    K: Eq + Hash + Clone,
+    F: for<'a> FnMut(&'a K) -> Fut,
+    Fut: Future<Output = V>,
+{
+    /// Creates a cache that treats entries older than `interval_ms` as stale.
+    pub fn new(func: F, interval_ms: f64) -> Self {
+        Self {
+            func,
+            cache: HashMap::new(),
+            interval: interval_ms,
+        }
+    }
+
+    /// Returns the cached value for `key`, refreshing it via `func` first if
+    /// it is missing or stale.
+    pub async fn get(&mut self, key: K) -> &V {
+        let interval = self.interval;
+        self.get_with_ttl(key, |_| interval).await
+    }
+
+    /// Like [`Self::get`], but `ttl` computes how long the freshly fetched
+    /// value should stay fresh for, based on the value itself, instead of
+    /// always using the cache's configured `interval`. Use this when only
+    /// some results deserve the full interval — e.g. a transient network
+    /// error shouldn't poison the cache as a confirmed negative for as long
+    /// as a genuine result would.
+    pub async fn get_with_ttl(&mut self, key: K, ttl: impl FnOnce(&V) -> f64) -> &V {
+        let now = now_ms();
+        let stale = match self.cache.get(&key) {
+            Some((stored, entry_ttl, _)) => now - stored > *entry_ttl,
+            None => true,
+        };
+
+        if stale {
+            let value = (self.func)(&key).await;
+            let entry_ttl = ttl(&value);
+            self.cache.insert(key.clone(), (now, entry_ttl, value));
+        }
+
+        &self.cache.get(&key).expect("just inserted or already fresh").2
+    }
+
+    /// Drops every cached entry, forcing the next `get` for any key to miss.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Removes a single cached entry, forcing the next `get` for `key` to miss.
+    pub fn invalidate(&mut self, key: &K) {
+        self.cache.remove(key);
+    }
+}
+
+pub(crate) fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        chrono::Utc::now().timestamp_millis() as f64
+    }
+}
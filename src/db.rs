@@ -1,19 +1,19 @@
 //! Database logic for Ziggy.
-//! 
+//!
 //! MIT License
-//! 
+//!
 //! Copyright (c) 2024 RustyNova (Original Logic)
-//! 
+//!
 //! Permission is hereby granted, free of charge, to any person obtaining a copy
 //! of this software and associated documentation files (the "Software"), to deal
 //! in the Software without restriction, including without limitation the rights
 //! to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
 //! copies of the Software, and to permit persons to whom the Software is
 //! furnished to do so, subject to the following conditions:
-//! 
+//!
 //! The above copyright notice and this permission notice shall be included in all
 //! copies or substantial portions of the Software.
-//! 
+//!
 //! THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
 //! IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
 //! FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
@@ -27,6 +27,83 @@ use std::collections::HashMap;
 use crate::models::{Listen, MbidMapping};
 use serde::{Deserialize, Serialize};
 
+/// Structured error surface for the `db` module.
+///
+/// IndexedDB failures split into two kinds: recoverable ones a caller can
+/// retry (a transaction abort, a (de)serialization hiccup) and fatal ones
+/// that mean the store itself is unusable (it failed to open, or the schema
+/// doesn't match what this build expects). Keeping them distinct lets the
+/// Leptos layer decide whether to retry silently or halt on a blocking
+/// error, instead of treating every failure the same way a bare `String`
+/// forced it to.
+#[derive(Clone, Debug)]
+pub enum DbError {
+    /// A transient failure — the same call is likely to succeed if retried.
+    Recoverable(String),
+    /// The database itself can't be used; retrying won't help.
+    Fatal(String),
+}
+
+impl DbError {
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, DbError::Fatal(_))
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            DbError::Recoverable(msg) | DbError::Fatal(msg) => msg,
+        }
+    }
+}
+
+impl std::fmt::Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbError::Recoverable(msg) => write!(f, "recoverable db error: {}", msg),
+            DbError::Fatal(msg) => write!(f, "fatal db error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+// Lets existing `Result<_, String>` call sites keep using `?` unchanged
+// while the db module itself reports which errors are worth retrying.
+impl From<DbError> for String {
+    fn from(err: DbError) -> String {
+        err.to_string()
+    }
+}
+
+impl From<rexie::Error> for DbError {
+    fn from(err: rexie::Error) -> Self {
+        // rexie doesn't distinguish "store missing"/"version mismatch" from
+        // "transaction aborted" at the type level, so we key off what the
+        // failure actually means: anything about the database's shape is
+        // fatal, a hiccup mid-transaction is worth retrying.
+        let msg = err.to_string();
+        let lower = msg.to_lowercase();
+        let fatal = lower.contains("version")
+            || lower.contains("already exists")
+            || lower.contains("not found")
+            || lower.contains("not supported");
+        if fatal {
+            DbError::Fatal(msg)
+        } else {
+            DbError::Recoverable(msg)
+        }
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for DbError {
+    fn from(err: serde_wasm_bindgen::Error) -> Self {
+        DbError::Recoverable(format!("serialization error: {}", err))
+    }
+}
+
+/// Shorthand for `Result<T, DbError>`.
+pub type DbResult<T> = Result<T, DbError>;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CanonicalMapping {
     pub msid: String,
@@ -40,9 +117,34 @@ pub struct AlbumMetadata {
     pub track_count: usize,
 }
 
-pub async fn init_db() -> Result<Rexie, String> {
+/// The configured ListenBrainz instance/token, persisted under a fixed key
+/// so the app remembers a self-hosted server across reloads.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AppConfig {
+    pub id: String,
+    pub instance: String,
+    pub token: String,
+}
+
+/// Fixed key `AppConfig` is stored under — there is only ever one active config.
+pub const APP_CONFIG_KEY: &str = "default";
+
+/// Tracks how far the `sync` subsystem has gotten, so an interrupted
+/// backfill resumes instead of restarting from scratch.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SyncCursor {
+    pub id: String,
+    pub newest_forward_ts: Option<i64>,
+    pub oldest_backfilled_ts: Option<i64>,
+    pub backfill_complete: bool,
+}
+
+/// Fixed key `SyncCursor` is stored under — there is only ever one cursor.
+pub const SYNC_CURSOR_KEY: &str = "default";
+
+pub async fn init_db() -> DbResult<Rexie> {
     let rexie = Rexie::builder("bowie_tracker_db")
-        .version(3) // Incremented for album_metadata store
+        .version(5) // Incremented for sync_cursor store
         .add_object_store(
             ObjectStore::new("listens")
                 .key_path("inserted_at")
@@ -56,22 +158,27 @@ pub async fn init_db() -> Result<Rexie, String> {
             ObjectStore::new("album_metadata")
                 .key_path("release_group_mbid")
         )
+        .add_object_store(
+            ObjectStore::new("config")
+                .key_path("id")
+        )
+        .add_object_store(
+            ObjectStore::new("sync_cursor")
+                .key_path("id")
+        )
         .build()
-        .await
-        .map_err(|e| e.to_string())?;
+        .await?;
     Ok(rexie)
 }
 
-pub async fn add_listens(db: &Rexie, listens: Vec<Listen>) -> Result<(), String> {
-    let transaction = db.transaction(&["listens", "mappings"], TransactionMode::ReadWrite)
-        .map_err(|e| e.to_string())?;
-    let listens_store = transaction.store("listens").map_err(|e| e.to_string())?;
-    let mappings_store = transaction.store("mappings").map_err(|e| e.to_string())?;
+pub async fn add_listens(db: &Rexie, listens: Vec<Listen>) -> DbResult<()> {
+    let transaction = db.transaction(&["listens", "mappings"], TransactionMode::ReadWrite)?;
+    let listens_store = transaction.store("listens")?;
+    let mappings_store = transaction.store("mappings")?;
 
     for listen in listens {
-        let listen_js = serde_wasm_bindgen::to_value(&listen)
-            .map_err(|e| format!("Serialization error: {}", e))?;
-        listens_store.put(&listen_js, None).await.map_err(|e| e.to_string())?;
+        let listen_js = serde_wasm_bindgen::to_value(&listen)?;
+        listens_store.put(&listen_js, None).await?;
 
         if let Some(mapping) = &listen.track_metadata.mbid_mapping {
             let artist_name = mapping.artists.as_ref()
@@ -84,68 +191,126 @@ pub async fn add_listens(db: &Rexie, listens: Vec<Listen>) -> Result<(), String>
                     recording_name: rec_name.clone(),
                     artist_name: artist,
                 };
-                let mapping_js = serde_wasm_bindgen::to_value(&canonical)
-                    .map_err(|e| format!("Serialization error: {}", e))?;
-                
-                mappings_store.put(&mapping_js, None).await.map_err(|e| e.to_string())?;
+                let mapping_js = serde_wasm_bindgen::to_value(&canonical)?;
+
+                mappings_store.put(&mapping_js, None).await?;
             }
         }
     }
-    
-    transaction.done().await.map_err(|e| e.to_string())?;
+
+    transaction.done().await?;
     Ok(())
 }
 
-pub async fn get_all_listens(db: &Rexie) -> Result<Vec<Listen>, String> {
-    let transaction = db.transaction(&["listens"], TransactionMode::ReadOnly).map_err(|e| e.to_string())?;
-    let store = transaction.store("listens").map_err(|e| e.to_string())?;
-    let all = store.get_all(None, None, None, None).await.map_err(|e| e.to_string())?;
-    
+pub async fn get_all_listens(db: &Rexie) -> DbResult<Vec<Listen>> {
+    let transaction = db.transaction(&["listens"], TransactionMode::ReadOnly)?;
+    let store = transaction.store("listens")?;
+    let all = store.get_all(None, None, None, None).await?;
+
     let mut listens = Vec::new();
-    for (_key, value) in all { 
-        let listen: Listen = serde_wasm_bindgen::from_value(value)
-            .map_err(|e| format!("Deserialization error: {}", e))?;
+    for (_key, value) in all {
+        let listen: Listen = serde_wasm_bindgen::from_value(value)?;
         listens.push(listen);
     }
     Ok(listens)
 }
 
-pub async fn get_max_timestamp(db: &Rexie) -> Result<Option<i64>, String> {
-    let transaction = db.transaction(&["listens"], TransactionMode::ReadOnly).map_err(|e| e.to_string())?;
-    let store = transaction.store("listens").map_err(|e| e.to_string())?;
-    let index = store.index("listened_at").map_err(|e| e.to_string())?;
-    
-    let latest = index.get_all(None, Some(1), None, Some(Direction::Prev))
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn get_max_timestamp(db: &Rexie) -> DbResult<Option<i64>> {
+    let transaction = db.transaction(&["listens"], TransactionMode::ReadOnly)?;
+    let store = transaction.store("listens")?;
+    let index = store.index("listened_at")?;
+
+    let latest = index.get_all(None, Some(1), None, Some(Direction::Prev)).await?;
 
     if let Some((_key, value)) = latest.into_iter().next() {
-        let listen: Listen = serde_wasm_bindgen::from_value(value)
-            .map_err(|e| format!("Deserialization error: {}", e))?;
+        let listen: Listen = serde_wasm_bindgen::from_value(value)?;
         return Ok(Some(listen.listened_at));
     }
 
     Ok(None)
 }
 
-pub async fn save_album_metadata(db: &Rexie, meta: AlbumMetadata) -> Result<(), String> {
-    let transaction = db.transaction(&["album_metadata"], TransactionMode::ReadWrite).map_err(|e| e.to_string())?;
-    let store = transaction.store("album_metadata").map_err(|e| e.to_string())?;
-    let js_val = serde_wasm_bindgen::to_value(&meta).map_err(|e| e.to_string())?;
-    store.put(&js_val, None).await.map_err(|e| e.to_string())?;
-    transaction.done().await.map_err(|e| e.to_string())?;
+pub async fn save_album_metadata(db: &Rexie, meta: AlbumMetadata) -> DbResult<()> {
+    let transaction = db.transaction(&["album_metadata"], TransactionMode::ReadWrite)?;
+    let store = transaction.store("album_metadata")?;
+    let js_val = serde_wasm_bindgen::to_value(&meta)?;
+    store.put(&js_val, None).await?;
+    transaction.done().await?;
+    Ok(())
+}
+
+/// Writes several `album_metadata` entries in a single transaction, for
+/// callers (like the concurrent MBID enrichment pipeline) that resolve a
+/// whole batch of release groups before persisting any of them.
+pub async fn save_album_metadata_batch(db: &Rexie, entries: Vec<AlbumMetadata>) -> DbResult<()> {
+    let transaction = db.transaction(&["album_metadata"], TransactionMode::ReadWrite)?;
+    let store = transaction.store("album_metadata")?;
+    for meta in entries {
+        let js_val = serde_wasm_bindgen::to_value(&meta)?;
+        store.put(&js_val, None).await?;
+    }
+    transaction.done().await?;
     Ok(())
 }
 
-pub async fn get_all_album_metadata(db: &Rexie) -> Result<HashMap<String, usize>, String> {
-    let transaction = db.transaction(&["album_metadata"], TransactionMode::ReadOnly).map_err(|e| e.to_string())?;
-    let store = transaction.store("album_metadata").map_err(|e| e.to_string())?;
-    let all = store.get_all(None, None, None, None).await.map_err(|e| e.to_string())?;
-    
+pub async fn get_all_album_metadata(db: &Rexie) -> DbResult<HashMap<String, usize>> {
+    let transaction = db.transaction(&["album_metadata"], TransactionMode::ReadOnly)?;
+    let store = transaction.store("album_metadata")?;
+    let all = store.get_all(None, None, None, None).await?;
+
     let mut map = HashMap::new();
     for (_key, value) in all {
-        let meta: AlbumMetadata = serde_wasm_bindgen::from_value(value).map_err(|e| e.to_string())?;
+        let meta: AlbumMetadata = serde_wasm_bindgen::from_value(value)?;
         map.insert(meta.release_group_mbid, meta.track_count);
     }
     Ok(map)
 }
+
+pub async fn save_config(db: &Rexie, instance: String, token: String) -> DbResult<()> {
+    let transaction = db.transaction(&["config"], TransactionMode::ReadWrite)?;
+    let store = transaction.store("config")?;
+    let config = AppConfig { id: APP_CONFIG_KEY.to_string(), instance, token };
+    let js_val = serde_wasm_bindgen::to_value(&config)?;
+    store.put(&js_val, None).await?;
+    transaction.done().await?;
+    Ok(())
+}
+
+pub async fn get_config(db: &Rexie) -> DbResult<Option<AppConfig>> {
+    let transaction = db.transaction(&["config"], TransactionMode::ReadOnly)?;
+    let store = transaction.store("config")?;
+    let key = serde_wasm_bindgen::to_value(APP_CONFIG_KEY)?;
+    let value = store.get(key).await?;
+
+    match value {
+        Some(value) => {
+            let config: AppConfig = serde_wasm_bindgen::from_value(value)?;
+            Ok(Some(config))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn save_sync_cursor(db: &Rexie, cursor: &SyncCursor) -> DbResult<()> {
+    let transaction = db.transaction(&["sync_cursor"], TransactionMode::ReadWrite)?;
+    let store = transaction.store("sync_cursor")?;
+    let js_val = serde_wasm_bindgen::to_value(cursor)?;
+    store.put(&js_val, None).await?;
+    transaction.done().await?;
+    Ok(())
+}
+
+pub async fn get_sync_cursor(db: &Rexie) -> DbResult<Option<SyncCursor>> {
+    let transaction = db.transaction(&["sync_cursor"], TransactionMode::ReadOnly)?;
+    let store = transaction.store("sync_cursor")?;
+    let key = serde_wasm_bindgen::to_value(SYNC_CURSOR_KEY)?;
+    let value = store.get(key).await?;
+
+    match value {
+        Some(value) => {
+            let cursor: SyncCursor = serde_wasm_bindgen::from_value(value)?;
+            Ok(Some(cursor))
+        }
+        None => Ok(None),
+    }
+}
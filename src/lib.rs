@@ -1,7 +1,16 @@
 pub mod models;
 pub mod analytics;
 pub mod charts;
+pub mod cache;
+pub mod api;
+pub mod musicbrainz;
+pub mod library;
+pub mod resolution;
 
 // Conditional compilation for db module since it depends on WASM-only rexie
 #[cfg(target_arch = "wasm32")]
 pub mod db;
+
+// sync drives db, so it's gated the same way
+#[cfg(target_arch = "wasm32")]
+pub mod sync;
@@ -0,0 +1,137 @@
+//! Local collection backends, used to tell "tracks you've streamed" apart
+//! from "tracks you actually own a file for".
+//!
+//! Dashboard completion defaults to listen history; resolving a
+//! [`LibraryBackend`] against a [`BowieDatabase`] turns that into a second,
+//! independent completion number — what you actually own — for
+//! `analytics::calculate_metrics` to report alongside it.
+
+use crate::models::BowieDatabase;
+use std::collections::{HashMap, HashSet};
+
+/// One track as reported by a local collection manager, resolved just
+/// enough to match it against a [`BowieTrack`](crate::models::BowieTrack).
+#[derive(Clone, Debug)]
+pub struct LocalTrack {
+    pub mbid: Option<String>,
+    pub title: String,
+    pub album: String,
+}
+
+/// Something that can enumerate the tracks in a local collection.
+pub trait LibraryBackend {
+    /// Lists every track the backend knows about.
+    fn tracks(&self) -> Result<Vec<LocalTrack>, String>;
+}
+
+/// Lowercases and collapses whitespace — the same laxness
+/// `analytics::get_bowie_album_tracks`'s string fallback already relies on.
+fn normalize(s: &str) -> String {
+    s.to_lowercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Resolves every track `backend` reports against `db`, MBID-first and
+/// falling back to a normalized title+album match — the same precedence
+/// `analytics::calculate_metrics` already uses for duration lookups — and
+/// returns the recording MBIDs confirmed owned.
+pub fn resolve_owned_tracks(
+    backend: &dyn LibraryBackend,
+    db: &BowieDatabase,
+) -> Result<HashSet<String>, String> {
+    let local_tracks = backend.tracks()?;
+
+    let mut known_mbids = HashSet::new();
+    let mut by_title_album: HashMap<(String, String), String> = HashMap::new();
+    for rg in db.release_groups.values() {
+        let mut album_keys = vec![normalize(&rg.title)];
+        album_keys.extend(rg.aliases.iter().map(|a| normalize(a)));
+
+        for track in &rg.tracks {
+            known_mbids.insert(track.id.clone());
+            for album_key in &album_keys {
+                by_title_album.insert((normalize(&track.title), album_key.clone()), track.id.clone());
+            }
+        }
+    }
+
+    let mut owned = HashSet::new();
+    for local in &local_tracks {
+        if let Some(mbid) = &local.mbid {
+            if known_mbids.contains(mbid) {
+                owned.insert(mbid.clone());
+                continue;
+            }
+        }
+
+        if let Some(mbid) = by_title_album.get(&(normalize(&local.title), normalize(&local.album))) {
+            owned.insert(mbid.clone());
+        }
+    }
+
+    Ok(owned)
+}
+
+/// Per-album ownership: `(title, owned tracks, total tracks)`, for
+/// `DashboardMetrics::ownership` to compare against listened completion.
+pub fn ownership_by_album(db: &BowieDatabase, owned: &HashSet<String>) -> Vec<(String, usize, usize)> {
+    let mut albums: Vec<(String, usize, usize)> = db
+        .release_groups
+        .values()
+        .map(|rg| {
+            let owned_count = rg.tracks.iter().filter(|t| owned.contains(&t.id)).count();
+            (rg.title.clone(), owned_count, rg.tracks.len())
+        })
+        .collect();
+    albums.sort_by(|a, b| a.0.cmp(&b.0));
+    albums
+}
+
+/// A track in a beets library export (`beet export -l <query> -f json`).
+/// Field names match beets' own flexible attribute template output.
+#[derive(Clone, Debug, serde::Deserialize)]
+struct BeetsExportTrack {
+    #[serde(default)]
+    mb_trackid: Option<String>,
+    title: String,
+    album: String,
+}
+
+/// Reads a beets collection from its JSON export.
+///
+/// Beets also ships a raw SQLite library (`library.db`), which would let a
+/// sync skip the `beet export` step entirely — but reading it needs a SQL
+/// driver this crate doesn't otherwise depend on, so that path isn't
+/// implemented here; the JSON export is the supported way in until one gets
+/// added.
+pub struct BeetsLibrary {
+    tracks: Vec<LocalTrack>,
+}
+
+impl BeetsLibrary {
+    /// Parses an in-memory beets JSON export, as produced by `beet export`.
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        let exported: Vec<BeetsExportTrack> = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        let tracks = exported
+            .into_iter()
+            .map(|t| LocalTrack {
+                mbid: t.mb_trackid,
+                title: t.title,
+                album: t.album,
+            })
+            .collect();
+        Ok(Self { tracks })
+    }
+
+    /// Reads and parses a beets JSON export from disk.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_json_file(path: impl AsRef<std::path::Path>) -> Result<Self, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::from_json(&raw)
+    }
+}
+
+impl LibraryBackend for BeetsLibrary {
+    fn tracks(&self) -> Result<Vec<LocalTrack>, String> {
+        Ok(self.tracks.clone())
+    }
+}
@@ -31,10 +31,99 @@ pub struct MBReleaseGroupResponse {
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MBRelease {
+    pub id: String,
+    pub status: Option<String>,
+    pub country: Option<String>,
+    pub date: Option<String>,
     #[serde(rename = "track-count")]
     pub track_count: usize,
 }
 
+/// One page of `GET /ws/2/release-group?artist=...`, used by
+/// [`crate::musicbrainz::sync`] to discover every release group credited to
+/// an artist before resolving each one individually.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBBrowseReleaseGroupsResponse {
+    #[serde(rename = "release-groups")]
+    pub release_groups: Vec<MBReleaseGroupSummary>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBReleaseGroupSummary {
+    pub id: String,
+    pub title: String,
+    #[serde(rename = "primary-type")]
+    pub primary_type: Option<String>,
+    #[serde(rename = "secondary-types")]
+    #[serde(default)]
+    pub secondary_types: Vec<String>,
+    #[serde(rename = "first-release-date")]
+    pub first_release_date: Option<String>,
+}
+
+/// `GET /ws/2/release/<id>?inc=recordings` — enough of a release's media
+/// list to build a release group's tracklist with recording MBIDs and
+/// durations.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBReleaseDetail {
+    pub media: Vec<MBMedium>,
+    #[serde(rename = "cover-art-archive")]
+    pub cover_art_archive: Option<MBCoverArtArchive>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBCoverArtArchive {
+    pub front: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBMedium {
+    pub tracks: Vec<MBTrackDetail>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBTrackDetail {
+    pub title: String,
+    pub length: Option<i64>,
+    pub recording: MBRecording,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBRecording {
+    pub id: String,
+    pub length: Option<i64>,
+}
+
+/// `GET /ws/2/recording?query=...` — used by
+/// [`crate::resolution`] to search for a recording matching an
+/// artist/track pair that arrived without its own MBID.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBRecordingSearchResponse {
+    pub recordings: Vec<MBRecordingSearchResult>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBRecordingSearchResult {
+    pub id: String,
+    pub title: String,
+    pub length: Option<i64>,
+    #[serde(rename = "artist-credit")]
+    #[serde(default)]
+    pub artist_credit: Vec<MBArtistCredit>,
+    #[serde(default)]
+    pub releases: Vec<MBRecordingRelease>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBArtistCredit {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MBRecordingRelease {
+    pub title: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Payload {
     pub count: i64,
@@ -93,14 +182,223 @@ pub struct BowieDatabase {
     pub release_groups: std::collections::HashMap<String, BowieReleaseGroup>,
 }
 
+/// A MusicBrainz `first-release-date`, split into parts so albums sharing a
+/// year can still be ordered by month/day. `month`/`day` are `None` when
+/// MusicBrainz only gives a bare year or year-month (e.g. `"1977"`).
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct AlbumDate {
+    pub year: i32,
+    pub month: Option<u32>,
+    pub day: Option<u32>,
+}
+
+impl AlbumDate {
+    /// Parses MusicBrainz's `first-release-date`: `"1977"`, `"1977-01"`, or
+    /// `"1977-01-14"`.
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut parts = raw.split('-');
+        let year: i32 = parts.next()?.parse().ok()?;
+        let month = parts.next().and_then(|m| m.parse().ok());
+        let day = parts.next().and_then(|d| d.parse().ok());
+        Some(AlbumDate { year, month, day })
+    }
+
+    /// The decade this date falls in, e.g. 1977 -> 1970.
+    pub fn decade(&self) -> i32 {
+        (self.year / 10) * 10
+    }
+
+    fn sort_key(&self) -> (i32, u32, u32) {
+        (self.year, self.month.unwrap_or(0), self.day.unwrap_or(0))
+    }
+}
+
+/// Tie-breaker for releases that share a year (and possibly month), assigned
+/// during database build (e.g. browse order from MusicBrainz).
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AlbumSeq(pub u32);
+
+/// MusicBrainz's coarse release-group classification (`primary-type`).
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AlbumPrimaryType {
+    Album,
+    Single,
+    #[serde(rename = "EP")]
+    Ep,
+    Broadcast,
+    Other,
+}
+
+impl AlbumPrimaryType {
+    /// Maps MusicBrainz's `primary-type` string, defaulting unknown types to `Other`.
+    pub fn parse(raw: &str) -> Self {
+        match raw {
+            "Album" => AlbumPrimaryType::Album,
+            "Single" => AlbumPrimaryType::Single,
+            "EP" => AlbumPrimaryType::Ep,
+            "Broadcast" => AlbumPrimaryType::Broadcast,
+            _ => AlbumPrimaryType::Other,
+        }
+    }
+}
+
+/// MusicBrainz's `secondary-types` tags (a release group can carry several,
+/// e.g. a live album can also be a compilation).
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum AlbumSecondaryType {
+    Live,
+    Compilation,
+    Soundtrack,
+    Remix,
+    #[serde(rename = "DJ-mix")]
+    DjMix,
+    Demo,
+}
+
+impl AlbumSecondaryType {
+    /// Maps one of MusicBrainz's `secondary-types` entries; `None` for tags
+    /// this tracker doesn't distinguish (e.g. "Mixtape/Street").
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "Live" => Some(AlbumSecondaryType::Live),
+            "Compilation" => Some(AlbumSecondaryType::Compilation),
+            "Soundtrack" => Some(AlbumSecondaryType::Soundtrack),
+            "Remix" => Some(AlbumSecondaryType::Remix),
+            "DJ-mix" => Some(AlbumSecondaryType::DjMix),
+            "Demo" => Some(AlbumSecondaryType::Demo),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct BowieReleaseGroup {
     pub title: String,
-    #[serde(rename = "type")]
-    pub release_type: Option<String>,
+    /// Other spellings/formattings this release group is known by — e.g. a
+    /// scrobble's `release_name` disagreeing with MusicBrainz's `title` on
+    /// punctuation or a remaster suffix. Populated by [`Merge::merge_in_place`]
+    /// when two sourced records for the same MBID carry different titles.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub primary_type: Option<AlbumPrimaryType>,
+    pub secondary_types: Vec<AlbumSecondaryType>,
     pub track_count: usize,
     pub image_url: Option<String>,
     pub tracks: Vec<BowieTrack>,
+    pub first_release_date: Option<AlbumDate>,
+    pub seq: AlbumSeq,
+}
+
+impl BowieReleaseGroup {
+    /// A studio album with no Live/Compilation secondary type — the "core
+    /// discography" cut used to keep completion metrics from being diluted
+    /// by live albums and compilations.
+    pub fn is_studio_album(&self) -> bool {
+        self.primary_type == Some(AlbumPrimaryType::Album)
+            && !self.secondary_types.iter().any(|t| {
+                matches!(t, AlbumSecondaryType::Live | AlbumSecondaryType::Compilation)
+            })
+    }
+
+    /// Whether `name` matches this release group's title or any known alias,
+    /// case-insensitively — the shared lookup behind the string-fallback
+    /// matching in `analytics::get_bowie_album_tracks`.
+    pub fn matches_title(&self, name: &str) -> bool {
+        let name_low = name.to_lowercase();
+        self.title.to_lowercase() == name_low
+            || self.aliases.iter().any(|a| a.to_lowercase() == name_low)
+    }
+}
+
+/// Folds a second, independently-sourced record for the same entity into
+/// `self`, keeping whichever side is more complete rather than letting the
+/// two coexist as duplicates. Implemented for the pieces of a
+/// [`BowieDatabase`] that can legitimately be reported by more than one
+/// source (MusicBrainz browse results refetched on re-sync, a previously
+/// cached build) under slightly different spellings or with partial data.
+pub trait Merge {
+    fn merge_in_place(&mut self, other: Self);
+}
+
+impl Merge for BowieTrack {
+    fn merge_in_place(&mut self, other: Self) {
+        // Keep max - the same "longest known duration wins" rule the
+        // MBID/title duration indexes in `analytics::calculate_metrics` use.
+        if other.duration_ms > self.duration_ms {
+            self.duration_ms = other.duration_ms;
+        }
+        if self.title.is_empty() {
+            self.title = other.title;
+        }
+    }
+}
+
+impl Merge for BowieReleaseGroup {
+    fn merge_in_place(&mut self, other: Self) {
+        if other.title != self.title && !self.aliases.iter().any(|a| a == &other.title) {
+            self.aliases.push(other.title);
+        }
+        for alias in other.aliases {
+            if alias != self.title && !self.aliases.iter().any(|a| a == &alias) {
+                self.aliases.push(alias);
+            }
+        }
+
+        if self.primary_type.is_none() {
+            self.primary_type = other.primary_type;
+        }
+        for secondary in other.secondary_types {
+            if !self.secondary_types.contains(&secondary) {
+                self.secondary_types.push(secondary);
+            }
+        }
+
+        // Union tracks by recording MBID, folding duplicates via `BowieTrack`'s own merge.
+        for track in other.tracks {
+            match self.tracks.iter_mut().find(|t| t.id == track.id) {
+                Some(existing) => existing.merge_in_place(track),
+                None => self.tracks.push(track),
+            }
+        }
+        self.track_count = self.track_count.max(self.tracks.len());
+
+        if self.image_url.is_none() {
+            self.image_url = other.image_url;
+        }
+        if self.first_release_date.is_none() {
+            self.first_release_date = other.first_release_date;
+        }
+    }
+}
+
+impl Merge for BowieDatabase {
+    /// Folds `other`'s release groups into `self`, keyed by release-group
+    /// MBID — the single canonicalization point that keeps re-syncing or
+    /// combining sources from ever producing duplicate groups.
+    fn merge_in_place(&mut self, other: Self) {
+        for (mbid, rg) in other.release_groups {
+            match self.release_groups.get_mut(&mbid) {
+                Some(existing) => existing.merge_in_place(rg),
+                None => {
+                    self.release_groups.insert(mbid, rg);
+                }
+            }
+        }
+    }
+}
+
+/// Sort key for ordering release groups by release era: `(year, month, day,
+/// seq)`, with dateless releases sorted last. Lets chronological views (and
+/// tie-breaking albums released in the same year) stay release-order rather
+/// than MBID-map iteration order.
+pub fn release_sort_key(rg: &BowieReleaseGroup) -> (i32, u32, u32, u32) {
+    match &rg.first_release_date {
+        Some(date) => {
+            let (year, month, day) = date.sort_key();
+            (year, month, day, rg.seq.0)
+        }
+        None => (i32::MAX, 0, 0, rg.seq.0),
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
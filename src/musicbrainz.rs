@@ -0,0 +1,572 @@
+//! MusicBrainz release-group lookups used to populate `album_metadata`.
+//!
+//! The `album_metadata` store only ever gets read back in `charts.rs` — this
+//! module is what actually fills it in, by resolving each distinct
+//! release-group MBID seen in stored listens to an authoritative track count.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::cache::{now_ms, AsyncCache};
+use crate::models::MBReleaseGroupResponse;
+
+/// MusicBrainz asks well-behaved clients to stay at 1 request/second.
+const RATE_LIMIT_DELAY_MS: f64 = 1000.0;
+
+/// Identifies this app to MusicBrainz, per their API etiquette guidelines.
+/// `pub(crate)` so [`crate::resolution`]'s MusicBrainz backend can reuse it
+/// instead of declaring its own copy.
+pub(crate) const USER_AGENT: &str = "bowie-tracker/0.1 ( https://github.com/caminante-blanco/bowie-tracker )";
+
+/// Release-group metadata rarely changes, so cache hits stay good for a week.
+pub const RELEASE_GROUP_INTERVAL_MS: f64 = 7.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// A failed lookup isn't a confirmed answer — retry it well before the full
+/// [`RELEASE_GROUP_INTERVAL_MS`] instead of treating "MusicBrainz errored"
+/// as "this release group has no track count" for a week.
+pub const RELEASE_GROUP_ERROR_INTERVAL_MS: f64 = 60.0 * 1000.0;
+
+/// How many release-group lookups the enrichment pipeline runs in flight at
+/// once by default. MusicBrainz's own 1 req/sec limit is still enforced by
+/// [`RateLimiter`] underneath, so raising this mostly helps overlap network
+/// latency rather than raw request throughput.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// A token-bucket shared across however many callers are enriching release
+/// groups concurrently, so the pool as a whole — not just each individual
+/// caller — never exceeds MusicBrainz's 1 request/second guideline.
+#[derive(Clone)]
+pub struct RateLimiter {
+    next_allowed_ms: Arc<Mutex<f64>>,
+    interval_ms: f64,
+}
+
+impl RateLimiter {
+    pub fn new(interval_ms: f64) -> Self {
+        Self {
+            next_allowed_ms: Arc::new(Mutex::new(0.0)),
+            interval_ms,
+        }
+    }
+
+    /// Waits, if necessary, until the next slot in the bucket is free, then
+    /// reserves it for the caller.
+    pub async fn acquire(&self) {
+        let wait_ms = {
+            let mut next = self.next_allowed_ms.lock().unwrap();
+            let now = now_ms();
+            let start = now.max(*next);
+            *next = start + self.interval_ms;
+            start - now
+        };
+
+        if wait_ms > 0.0 {
+            gloo_timers::future::sleep(Duration::from_millis(wait_ms as u64)).await;
+        }
+    }
+}
+
+/// Builds the shared MusicBrainz rate limiter at the documented 1 req/sec.
+pub fn default_rate_limiter() -> RateLimiter {
+    RateLimiter::new(RATE_LIMIT_DELAY_MS)
+}
+
+/// Looks up the canonical track count for a release-group MBID, taking the
+/// largest `track-count` across its releases (a release-group usually has
+/// several pressings/editions that agree, but we err toward the fullest one).
+/// `limiter` gates the request so concurrent callers still respect
+/// MusicBrainz's combined 1 req/sec limit.
+pub async fn fetch_release_group_track_count(mbid: &str, limiter: &RateLimiter) -> Result<usize, String> {
+    limiter.acquire().await;
+
+    let url = format!(
+        "https://musicbrainz.org/ws/2/release-group/{}?inc=releases&fmt=json",
+        mbid
+    );
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(&url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let parsed: MBReleaseGroupResponse = resp.json().await.map_err(|e| e.to_string())?;
+    Ok(parsed.releases.iter().map(|r| r.track_count).max().unwrap_or(0))
+}
+
+/// Percent-encodes a MusicBrainz Lucene query string for use in a URL,
+/// without pulling in a dedicated URL-encoding dependency.
+fn percent_encode_query(raw: &str) -> String {
+    raw.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// [`crate::resolution::ResolutionBackend`] backed by MusicBrainz recording
+/// search — used to resolve scrobbles that arrived with no `mbid_mapping`
+/// at all.
+pub struct MusicBrainzSearch {
+    limiter: RateLimiter,
+}
+
+impl MusicBrainzSearch {
+    pub fn new() -> Self {
+        Self { limiter: default_rate_limiter() }
+    }
+}
+
+impl Default for MusicBrainzSearch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl crate::resolution::ResolutionBackend for MusicBrainzSearch {
+    fn search<'a>(
+        &'a self,
+        artist_name: &'a str,
+        track_name: &'a str,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Vec<crate::resolution::ResolutionCandidate>, String>> + 'a>> {
+        Box::pin(async move {
+            self.limiter.acquire().await;
+
+            let query = format!("artist:\"{}\" AND recording:\"{}\"", artist_name, track_name);
+            let url = format!(
+                "https://musicbrainz.org/ws/2/recording?query={}&fmt=json",
+                percent_encode_query(&query)
+            );
+
+            let client = reqwest::Client::new();
+            let resp = client
+                .get(&url)
+                .header("User-Agent", USER_AGENT)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let parsed: crate::models::MBRecordingSearchResponse =
+                resp.json().await.map_err(|e| e.to_string())?;
+
+            Ok(parsed
+                .recordings
+                .into_iter()
+                .map(|rec| crate::resolution::ResolutionCandidate {
+                    recording_mbid: rec.id,
+                    artist_credit: rec
+                        .artist_credit
+                        .first()
+                        .map(|a| a.name.clone())
+                        .unwrap_or_default(),
+                    duration_ms: rec.length,
+                    album: rec.releases.first().map(|r| r.title.clone()),
+                    title: rec.title,
+                })
+                .collect())
+        })
+    }
+}
+
+type ReleaseGroupCacheFn = Box<dyn FnMut(&String) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<usize, String>>>>>;
+
+/// Memoizes [`fetch_release_group_track_count`] per MBID for
+/// [`RELEASE_GROUP_INTERVAL_MS`], so re-syncing the same release group on
+/// every dashboard load doesn't re-query MusicBrainz. Errors are kept fresh
+/// for only [`RELEASE_GROUP_ERROR_INTERVAL_MS`] — see [`release_group_ttl`].
+pub type ReleaseGroupCache = AsyncCache<String, Result<usize, String>, ReleaseGroupCacheFn>;
+
+pub fn release_group_cache() -> ReleaseGroupCache {
+    let limiter = default_rate_limiter();
+    AsyncCache::new(
+        Box::new(move |mbid: &String| {
+            let mbid = mbid.clone();
+            let limiter = limiter.clone();
+            Box::pin(async move { fetch_release_group_track_count(&mbid, &limiter).await })
+        }),
+        RELEASE_GROUP_INTERVAL_MS,
+    )
+}
+
+/// The [`AsyncCache::get_with_ttl`] policy for [`ReleaseGroupCache`]: a
+/// resolved track count is good for [`RELEASE_GROUP_INTERVAL_MS`], but a
+/// lookup error is only a confirmed "try again soon", not a confirmed
+/// answer, so it's kept fresh for [`RELEASE_GROUP_ERROR_INTERVAL_MS`] instead.
+pub fn release_group_ttl(result: &Result<usize, String>) -> f64 {
+    match result {
+        Ok(_) => RELEASE_GROUP_INTERVAL_MS,
+        Err(_) => RELEASE_GROUP_ERROR_INTERVAL_MS,
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod enrichment {
+    use super::{
+        default_rate_limiter, fetch_release_group_track_count, release_group_ttl,
+        ReleaseGroupCache, DEFAULT_CONCURRENCY,
+    };
+    use crate::db::{self, AlbumMetadata};
+    use futures::stream::{self, StreamExt};
+    use rexie::Rexie;
+    use std::collections::HashSet;
+    use web_sys::console;
+
+    /// Fills in `album_metadata` for every MBID in `release_group_mbids` that
+    /// isn't already populated, one lookup at a time, using `cache` so
+    /// repeated calls for the same release group don't re-hit MusicBrainz.
+    pub async fn enrich_album_metadata(
+        db_handle: &Rexie,
+        cache: &mut ReleaseGroupCache,
+        release_group_mbids: &HashSet<String>,
+    ) -> Result<(), String> {
+        let known = db::get_all_album_metadata(db_handle).await?;
+
+        for mbid in release_group_mbids {
+            if known.contains_key(mbid) {
+                continue;
+            }
+
+            let track_count = cache.get_with_ttl(mbid.clone(), release_group_ttl).await.clone()?;
+            db::save_album_metadata(
+                db_handle,
+                AlbumMetadata {
+                    release_group_mbid: mbid.clone(),
+                    track_count,
+                },
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Fans out release-group lookups up to `concurrency` at a time via a
+    /// `buffer_unordered` stream, then writes every resolved track count to
+    /// `album_metadata` in a single batched transaction instead of one `put`
+    /// per item. A shared [`super::RateLimiter`] keeps the pool as a whole
+    /// within MusicBrainz's 1 req/sec limit regardless of how many lookups
+    /// are in flight. Returns the number of release groups newly enriched.
+    pub async fn enrich_album_metadata_concurrent(
+        db_handle: &Rexie,
+        release_group_mbids: &HashSet<String>,
+        concurrency: usize,
+    ) -> Result<usize, String> {
+        let known = db::get_all_album_metadata(db_handle).await?;
+        let limiter = default_rate_limiter();
+        let concurrency = concurrency.max(1);
+
+        let pending: Vec<String> = release_group_mbids
+            .iter()
+            .filter(|mbid| !known.contains_key(*mbid))
+            .cloned()
+            .collect();
+
+        let resolved: Vec<AlbumMetadata> = stream::iter(pending)
+            .map(|mbid| {
+                let limiter = limiter.clone();
+                async move {
+                    match fetch_release_group_track_count(&mbid, &limiter).await {
+                        Ok(track_count) => Some(AlbumMetadata { release_group_mbid: mbid, track_count }),
+                        Err(err) => {
+                            // Unlike `enrich_album_metadata`, which propagates
+                            // via `?`, a single bad MBID shouldn't fail the
+                            // whole batch — but it must not vanish silently
+                            // either, so the release group stays unpopulated
+                            // and visibly so, not just missing.
+                            console::warn_1(
+                                &format!("release-group lookup failed for {}: {}", mbid, err).into(),
+                            );
+                            None
+                        }
+                    }
+                }
+            })
+            .buffer_unordered(concurrency)
+            .filter_map(|result| async move { result })
+            .collect()
+            .await;
+
+        let enriched = resolved.len();
+        db::save_album_metadata_batch(db_handle, resolved).await?;
+        Ok(enriched)
+    }
+
+    /// [`enrich_album_metadata_concurrent`] with [`DEFAULT_CONCURRENCY`] in-flight lookups.
+    pub async fn enrich_album_metadata_concurrent_default(
+        db_handle: &Rexie,
+        release_group_mbids: &HashSet<String>,
+    ) -> Result<usize, String> {
+        enrich_album_metadata_concurrent(db_handle, release_group_mbids, DEFAULT_CONCURRENCY).await
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use enrichment::{enrich_album_metadata, enrich_album_metadata_concurrent, enrich_album_metadata_concurrent_default};
+
+/// Builds a [`crate::models::BowieDatabase`] from scratch by browsing
+/// MusicBrainz, instead of relying on the hardcoded track-count ladder in
+/// `analytics.rs` (which stays around only as a last-resort offline
+/// fallback for albums this sync hasn't covered yet).
+///
+/// This runs outside the browser — it writes its cache straight to disk —
+/// so it's gated to native builds, the same way the rest of this file is
+/// gated to wasm where it needs IndexedDB.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sync {
+    use super::{default_rate_limiter, RateLimiter, USER_AGENT};
+    use crate::models::{
+        AlbumDate, AlbumPrimaryType, AlbumSecondaryType, AlbumSeq, BowieDatabase,
+        BowieReleaseGroup, BowieTrack, MBBrowseReleaseGroupsResponse, MBRelease,
+        MBReleaseDetail, MBReleaseGroupResponse, MBReleaseGroupSummary,
+    };
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// MusicBrainz's MBID for the artist "David Bowie".
+    pub const DAVID_BOWIE_ARTIST_MBID: &str = "5441c29d-3602-4898-b1a1-b77fa23b8e50";
+
+    /// How many release groups MusicBrainz returns per browse page.
+    const PAGE_SIZE: usize = 100;
+
+    /// Where to fetch from and what to call the cache on disk while doing it.
+    pub struct SyncConfig {
+        pub user_agent: String,
+        pub cache_dir: PathBuf,
+    }
+
+    impl SyncConfig {
+        pub fn new(user_agent: impl Into<String>, cache_dir: impl Into<PathBuf>) -> Self {
+            Self {
+                user_agent: user_agent.into(),
+                cache_dir: cache_dir.into(),
+            }
+        }
+
+        /// Uses this crate's own [`USER_AGENT`] against `cache_dir`.
+        pub fn with_default_user_agent(cache_dir: impl Into<PathBuf>) -> Self {
+            Self::new(USER_AGENT, cache_dir)
+        }
+
+        fn cache_path(&self, release_group_mbid: &str) -> PathBuf {
+            self.cache_dir.join(format!("{}.json", release_group_mbid))
+        }
+
+        fn release_cache_path(&self, release_id: &str) -> PathBuf {
+            self.cache_dir.join(format!("release-{}.json", release_id))
+        }
+    }
+
+    async fn get_json(
+        url: &str,
+        user_agent: &str,
+        limiter: &RateLimiter,
+    ) -> Result<serde_json::Value, String> {
+        limiter.acquire().await;
+
+        let client = reqwest::Client::new();
+        let resp = client
+            .get(url)
+            .header("User-Agent", user_agent)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        resp.json().await.map_err(|e| e.to_string())
+    }
+
+    /// Pages through `GET /ws/2/release-group?artist=...` until MusicBrainz
+    /// returns a short page, collecting every release group credited to
+    /// `artist_mbid`.
+    async fn browse_release_groups(
+        artist_mbid: &str,
+        config: &SyncConfig,
+        limiter: &RateLimiter,
+    ) -> Result<Vec<MBReleaseGroupSummary>, String> {
+        let mut all = Vec::new();
+        let mut offset = 0;
+
+        loop {
+            let url = format!(
+                "https://musicbrainz.org/ws/2/release-group?artist={}&limit={}&offset={}&fmt=json",
+                artist_mbid, PAGE_SIZE, offset
+            );
+            let value = get_json(&url, &config.user_agent, limiter).await?;
+            let page: MBBrowseReleaseGroupsResponse =
+                serde_json::from_value(value).map_err(|e| e.to_string())?;
+
+            let page_len = page.release_groups.len();
+            all.extend(page.release_groups);
+
+            if page_len < PAGE_SIZE {
+                break;
+            }
+            offset += PAGE_SIZE;
+        }
+
+        Ok(all)
+    }
+
+    /// Fetches a release group's releases, reading from `config.cache_dir`
+    /// first so a re-sync only goes over the network for release groups that
+    /// weren't already resolved.
+    async fn fetch_release_group_detail(
+        summary: &MBReleaseGroupSummary,
+        config: &SyncConfig,
+        limiter: &RateLimiter,
+    ) -> Result<MBReleaseGroupResponse, String> {
+        let cache_path = config.cache_path(&summary.id);
+        if let Ok(raw) = fs::read_to_string(&cache_path) {
+            if let Ok(cached) = serde_json::from_str(&raw) {
+                return Ok(cached);
+            }
+        }
+
+        let url = format!(
+            "https://musicbrainz.org/ws/2/release-group/{}?inc=releases&fmt=json",
+            summary.id
+        );
+        let value = get_json(&url, &config.user_agent, limiter).await?;
+
+        if let Some(parent) = cache_path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        let _ = fs::write(&cache_path, value.to_string());
+
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    /// Picks the canonical release out of a release group: the earliest
+    /// official release, preferring worldwide (`XW`) over `GB`/`US` editions
+    /// when several share a date, since that's the edition MusicBrainz
+    /// conventionally tracks a release group's recordings against.
+    fn pick_canonical_release(detail: &MBReleaseGroupResponse) -> Option<&MBRelease> {
+        detail
+            .releases
+            .iter()
+            .filter(|r| r.status.as_deref() == Some("Official"))
+            .min_by_key(|r| {
+                let date = r
+                    .date
+                    .as_deref()
+                    .filter(|d| !d.trim().is_empty())
+                    .unwrap_or("9999")
+                    .to_string();
+                let country_rank = match r.country.as_deref() {
+                    Some("XW") => 0,
+                    Some("GB") => 1,
+                    Some("US") => 2,
+                    _ => 3,
+                };
+                (date, country_rank)
+            })
+            .or_else(|| detail.releases.first())
+    }
+
+    /// Fetches a release's tracklist (recording MBIDs + durations) and its
+    /// cover-art-archive front image, if any. Reads from `config.cache_dir`
+    /// first, same as [`fetch_release_group_detail`], so a re-sync only goes
+    /// over the network for releases that weren't already resolved — this is
+    /// the bulk of a sync's requests, one per release group.
+    async fn fetch_release_tracks(
+        release_id: &str,
+        config: &SyncConfig,
+        limiter: &RateLimiter,
+    ) -> Result<(Vec<BowieTrack>, Option<String>), String> {
+        let cache_path = config.release_cache_path(release_id);
+        let detail: MBReleaseDetail = if let Some(cached) = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+        {
+            cached
+        } else {
+            let url = format!(
+                "https://musicbrainz.org/ws/2/release/{}?inc=recordings&fmt=json",
+                release_id
+            );
+            let value = get_json(&url, &config.user_agent, limiter).await?;
+
+            if let Some(parent) = cache_path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let _ = fs::write(&cache_path, value.to_string());
+
+            serde_json::from_value(value).map_err(|e| e.to_string())?
+        };
+
+        let mut tracks = Vec::new();
+        for medium in detail.media {
+            for track in medium.tracks {
+                let duration_ms = track.length.or(track.recording.length).unwrap_or(0);
+                tracks.push(BowieTrack {
+                    id: track.recording.id,
+                    title: track.title,
+                    duration_ms,
+                });
+            }
+        }
+
+        let image_url = detail
+            .cover_art_archive
+            .filter(|c| c.front)
+            .map(|_| format!("https://coverartarchive.org/release/{}/front", release_id));
+
+        Ok((tracks, image_url))
+    }
+
+    /// Browses every release group credited to `artist_mbid`, resolves a
+    /// canonical release and its recordings for each, and assembles a full
+    /// [`BowieDatabase`]. Respects MusicBrainz's 1 request/second guideline
+    /// via a single [`RateLimiter`] shared across the whole sync, and caches
+    /// each release group's raw `releases` JSON and each canonical release's
+    /// `recordings` JSON under `config.cache_dir` so a later re-sync only
+    /// fetches release groups and releases that are missing from it.
+    pub async fn build_bowie_database(
+        artist_mbid: &str,
+        config: &SyncConfig,
+    ) -> Result<BowieDatabase, String> {
+        let limiter = default_rate_limiter();
+        let summaries = browse_release_groups(artist_mbid, config, &limiter).await?;
+
+        let mut release_groups = HashMap::new();
+        for (seq, summary) in summaries.iter().enumerate() {
+            let detail = fetch_release_group_detail(summary, config, &limiter).await?;
+            let canonical = match pick_canonical_release(&detail) {
+                Some(release) => release,
+                None => continue,
+            };
+
+            let (tracks, image_url) = fetch_release_tracks(&canonical.id, config, &limiter).await?;
+            let track_count = tracks.len().max(canonical.track_count);
+
+            release_groups.insert(
+                summary.id.clone(),
+                BowieReleaseGroup {
+                    title: summary.title.clone(),
+                    aliases: Vec::new(),
+                    primary_type: summary.primary_type.as_deref().map(AlbumPrimaryType::parse),
+                    secondary_types: summary
+                        .secondary_types
+                        .iter()
+                        .filter_map(|t| AlbumSecondaryType::parse(t))
+                        .collect(),
+                    track_count,
+                    image_url,
+                    tracks,
+                    first_release_date: summary
+                        .first_release_date
+                        .as_deref()
+                        .and_then(AlbumDate::parse),
+                    seq: AlbumSeq(seq as u32),
+                },
+            );
+        }
+
+        Ok(BowieDatabase { release_groups })
+    }
+}
@@ -0,0 +1,172 @@
+//! Cross-service resolution for scrobbles that arrive with no
+//! `mbid_mapping` at all — queries one or more search backends (MusicBrainz,
+//! and optionally a streaming-service search) for the best-matching
+//! recording, so `analytics::calculate_metrics`'s duration lookups don't
+//! have to fall back to zero for every unmapped play.
+
+use crate::cache::AsyncCache;
+use crate::models::{MappedArtist, MbidMapping};
+use std::future::Future;
+use std::pin::Pin;
+
+/// One candidate a [`ResolutionBackend`] found for an artist/track pair.
+#[derive(Clone, Debug)]
+pub struct ResolutionCandidate {
+    pub recording_mbid: String,
+    pub title: String,
+    pub artist_credit: String,
+    pub duration_ms: Option<i64>,
+    pub album: Option<String>,
+}
+
+/// A service that can search for recordings matching an artist/track pair.
+/// Implemented for MusicBrainz recording search in
+/// [`crate::musicbrainz::MusicBrainzSearch`]; a streaming-service search can
+/// implement the same trait and be added to a backend list passed to
+/// [`resolve_track`] without touching any call sites.
+pub trait ResolutionBackend {
+    fn search<'a>(
+        &'a self,
+        artist_name: &'a str,
+        track_name: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<ResolutionCandidate>, String>> + 'a>>;
+}
+
+/// A resolved match, shaped like what ListenBrainz itself would have sent
+/// in `mbid_mapping`, plus the duration used to pick it.
+#[derive(Clone, Debug)]
+pub struct ResolvedTrack {
+    pub mapping: MbidMapping,
+    pub duration_ms: i64,
+}
+
+/// The lowest title similarity [`best_match`] accepts — below this, a
+/// low-confidence guess is worse than leaving the listen unmapped.
+const MIN_TITLE_SIMILARITY: f64 = 0.5;
+
+/// Resolutions rarely change once found, so cache hits stay good for a
+/// month — the same order of magnitude as [`crate::musicbrainz::RELEASE_GROUP_INTERVAL_MS`].
+pub const RESOLUTION_INTERVAL_MS: f64 = 30.0 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// A backend error isn't a confirmed "no match" — retry well before the
+/// full [`RESOLUTION_INTERVAL_MS`] instead of memoizing a transient network
+/// failure as permanently unresolvable.
+pub const RESOLUTION_ERROR_INTERVAL_MS: f64 = 60.0 * 1000.0;
+
+/// Token-level Jaccard similarity between two titles, lowercased and split
+/// on whitespace. Cheap enough to run over every candidate a backend
+/// returns, and ignores case. Extra words such as a remaster suffix lower
+/// the score in proportion to the title's length, and punctuation attached
+/// to a word makes it a different token.
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let tokens = |s: &str| -> std::collections::HashSet<String> {
+        s.to_lowercase().split_whitespace().map(|w| w.to_string()).collect()
+    };
+    let ta = tokens(a);
+    let tb = tokens(b);
+    if ta.is_empty() && tb.is_empty() {
+        return 1.0;
+    }
+
+    let intersection = ta.intersection(&tb).count();
+    let union = ta.union(&tb).count().max(1);
+    intersection as f64 / union as f64
+}
+
+/// Picks the best of `candidates` for `track_name`: the artist credit must
+/// contain "bowie" (the same check `analytics::is_bowie_meta` falls back
+/// to), and among those, the highest title similarity wins.
+fn best_match(track_name: &str, candidates: Vec<ResolutionCandidate>) -> Option<ResolutionCandidate> {
+    candidates
+        .into_iter()
+        .filter(|c| c.artist_credit.to_lowercase().contains("bowie"))
+        .map(|c| (title_similarity(track_name, &c.title), c))
+        .filter(|(score, _)| *score >= MIN_TITLE_SIMILARITY)
+        .max_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap())
+        .map(|(_, c)| c)
+}
+
+/// Queries `backends` in order for `(artist_name, track_name)`, returning
+/// the best match of the first backend that has one.
+///
+/// `Ok(None)` means at least one backend was queried successfully and none
+/// of the reachable ones had a match — a genuine negative, safe to treat as
+/// stable, even if other backends errored. `Err` means no
+/// backend could be reached at all, so the caller shouldn't treat that the
+/// same as a confirmed "unresolvable" (see [`RESOLUTION_ERROR_INTERVAL_MS`]).
+pub async fn resolve_track(
+    backends: &[Box<dyn ResolutionBackend>],
+    artist_name: &str,
+    track_name: &str,
+) -> Result<Option<ResolvedTrack>, String> {
+    let mut last_err = None;
+    let mut any_reachable = false;
+
+    for backend in backends {
+        let candidates = match backend.search(artist_name, track_name).await {
+            Ok(candidates) => candidates,
+            Err(err) => {
+                last_err = Some(err);
+                continue;
+            }
+        };
+        any_reachable = true;
+
+        if let Some(candidate) = best_match(track_name, candidates) {
+            return Ok(Some(ResolvedTrack {
+                duration_ms: candidate.duration_ms.unwrap_or(0),
+                mapping: MbidMapping {
+                    recording_name: Some(candidate.title),
+                    recording_mbid: Some(candidate.recording_mbid),
+                    artists: Some(vec![MappedArtist { artist_credit_name: candidate.artist_credit }]),
+                    release_name: candidate.album,
+                },
+            }));
+        }
+    }
+
+    // At least one backend was reachable and came back empty-handed — a
+    // confirmed no-match, even if another backend errored. Only surface an
+    // error when *no* backend could be queried at all.
+    if any_reachable {
+        Ok(None)
+    } else {
+        Err(last_err.unwrap_or_else(|| "no resolution backends configured".to_string()))
+    }
+}
+
+type ResolutionFn = Box<
+    dyn FnMut(&(String, String)) -> Pin<Box<dyn Future<Output = Result<Option<ResolvedTrack>, String>>>>,
+>;
+
+/// Memoizes [`resolve_track`] by `(artist_name, track_name)` for
+/// [`RESOLUTION_INTERVAL_MS`], so repeated unmapped scrobbles for the same
+/// track resolve once rather than re-querying every dashboard load. A
+/// backend error is kept fresh for only [`RESOLUTION_ERROR_INTERVAL_MS`] —
+/// see [`resolution_ttl`].
+pub type ResolutionCache = AsyncCache<(String, String), Result<Option<ResolvedTrack>, String>, ResolutionFn>;
+
+/// The [`AsyncCache::get_with_ttl`] policy for [`ResolutionCache`]: a
+/// result — found or confirmed no-match — is good for
+/// [`RESOLUTION_INTERVAL_MS`], but a lookup error means no backend was even
+/// reachable, so it's retried after [`RESOLUTION_ERROR_INTERVAL_MS`] instead.
+pub fn resolution_ttl(result: &Result<Option<ResolvedTrack>, String>) -> f64 {
+    match result {
+        Ok(_) => RESOLUTION_INTERVAL_MS,
+        Err(_) => RESOLUTION_ERROR_INTERVAL_MS,
+    }
+}
+
+/// Builds a [`ResolutionCache`] backed by `backends`, shared via `Rc` since
+/// the cache's lookup closure is called repeatedly and can't take the
+/// backend list by value.
+pub fn resolution_cache(backends: std::rc::Rc<Vec<Box<dyn ResolutionBackend>>>) -> ResolutionCache {
+    AsyncCache::new(
+        Box::new(move |key: &(String, String)| {
+            let backends = backends.clone();
+            let (artist_name, track_name) = key.clone();
+            Box::pin(async move { resolve_track(&backends, &artist_name, &track_name).await })
+        }),
+        RESOLUTION_INTERVAL_MS,
+    )
+}
@@ -0,0 +1,124 @@
+//! Resumable full-history ingestion.
+//!
+//! Two passes drive the local IndexedDB copy of a user's listens toward the
+//! full ListenBrainz history: `forward_sync` pages forward from whatever is
+//! already stored using `min_ts`, and `backfill` walks backward using
+//! `max_ts` until the API returns an empty payload. Progress for both is
+//! persisted as a [`db::SyncCursor`] so an interrupted backfill resumes
+//! instead of restarting from the newest listen every time.
+
+use crate::api::RequestContext;
+use crate::db::{self, SyncCursor};
+use crate::models::ListenBrainzResponse;
+use rexie::Rexie;
+
+const PAGE_COUNT: usize = 100;
+
+fn empty_cursor() -> SyncCursor {
+    SyncCursor {
+        id: db::SYNC_CURSOR_KEY.to_string(),
+        newest_forward_ts: None,
+        oldest_backfilled_ts: None,
+        backfill_complete: false,
+    }
+}
+
+/// Pulls every listen newer than what's already stored. ListenBrainz always
+/// returns listens newest-first, so `min_ts` stays anchored at the stored
+/// max for every page — it's what separates "new" listens from history
+/// `backfill` already owns — while `max_ts` descends to the oldest
+/// `listened_at` seen in the previous page, the same windowing `backfill`
+/// uses in the opposite direction. Stops when a page comes back smaller
+/// than [`PAGE_COUNT`] (i.e. we've caught up to "now"). Returns the number
+/// of listens ingested.
+pub async fn forward_sync(ctx: &RequestContext, db_handle: &Rexie, user: &str) -> Result<usize, String> {
+    let min_ts = db::get_max_timestamp(db_handle).await?;
+    let mut max_ts = None;
+    let mut total = 0;
+
+    loop {
+        let mut path = format!("/1/user/{}/listens?count={}", user, PAGE_COUNT);
+        if let Some(ts) = min_ts {
+            path.push_str(&format!("&min_ts={}", ts));
+        }
+        if let Some(ts) = max_ts {
+            path.push_str(&format!("&max_ts={}", ts));
+        }
+
+        let resp = ctx.fetch_with_rate_limit(&path).await?;
+        let json: ListenBrainzResponse = resp.json().await.map_err(|e| e.to_string())?;
+        let listens = json.payload.listens;
+
+        if listens.is_empty() {
+            break;
+        }
+
+        let newest = listens.iter().map(|l| l.listened_at).max();
+        let oldest = listens.iter().map(|l| l.listened_at).min();
+        let page_len = listens.len();
+        db::add_listens(db_handle, listens).await?;
+        total += page_len;
+
+        let mut cursor = db::get_sync_cursor(db_handle).await?.unwrap_or_else(empty_cursor);
+        cursor.newest_forward_ts = newest.max(cursor.newest_forward_ts);
+        db::save_sync_cursor(db_handle, &cursor).await?;
+
+        if page_len < PAGE_COUNT {
+            break;
+        }
+        max_ts = oldest;
+    }
+
+    Ok(total)
+}
+
+/// One-time walk backwards through the full history, decrementing `max_ts`
+/// to the oldest `listened_at` seen in each page until the API returns an
+/// empty payload. Resumes from the persisted cursor if a previous run was
+/// interrupted, and is a no-op once `backfill_complete` is set. Returns the
+/// number of listens ingested.
+pub async fn backfill(ctx: &RequestContext, db_handle: &Rexie, user: &str) -> Result<usize, String> {
+    let mut cursor = db::get_sync_cursor(db_handle).await?.unwrap_or_else(empty_cursor);
+    if cursor.backfill_complete {
+        return Ok(0);
+    }
+
+    let mut max_ts = cursor.oldest_backfilled_ts;
+    let mut total = 0;
+
+    loop {
+        let mut path = format!("/1/user/{}/listens?count={}", user, PAGE_COUNT);
+        if let Some(ts) = max_ts {
+            path.push_str(&format!("&max_ts={}", ts));
+        }
+
+        let resp = ctx.fetch_with_rate_limit(&path).await?;
+        let json: ListenBrainzResponse = resp.json().await.map_err(|e| e.to_string())?;
+        let listens = json.payload.listens;
+
+        if listens.is_empty() {
+            cursor.backfill_complete = true;
+            db::save_sync_cursor(db_handle, &cursor).await?;
+            break;
+        }
+
+        let oldest = listens.iter().map(|l| l.listened_at).min();
+        total += listens.len();
+        db::add_listens(db_handle, listens).await?;
+
+        cursor.oldest_backfilled_ts = oldest;
+        db::save_sync_cursor(db_handle, &cursor).await?;
+
+        max_ts = oldest;
+    }
+
+    Ok(total)
+}
+
+/// Runs forward-sync then backfill, for a single resumable catch-up call.
+/// Returns `(listens pulled forward, listens pulled backward)`.
+pub async fn sync_all(ctx: &RequestContext, db_handle: &Rexie, user: &str) -> Result<(usize, usize), String> {
+    let forward = forward_sync(ctx, db_handle, user).await?;
+    let backward = backfill(ctx, db_handle, user).await?;
+    Ok((forward, backward))
+}